@@ -1,7 +1,16 @@
 use std::io;
 use std::fmt;
+use std::time::{ Duration, Instant };
+use rand::SeedableRng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 
+mod minimax;
+
+// Fixed seed for the search's RNG, so a run can be replayed and `get_new_state`
+// and the rollout policy can be covered by deterministic regression tests.
+const INIT_SEED: u64 = 0xC0FFEE;
+
 macro_rules! parse_input {
     ($x:expr, $t:ident) => ($x.trim().parse::<$t>().unwrap());
 }
@@ -197,6 +206,7 @@ struct GameState {
     nutrients: i32,
     sun: i32,
     score: i32,
+    is_waiting: bool,
     op_sun: i32,
     op_score: i32,
     op_is_waiting: bool,
@@ -229,6 +239,7 @@ fn get_game_state(area: Area) -> GameState {
         nutrients,
         sun,
         score,
+        is_waiting: false,
         op_sun,
         op_score,
         op_is_waiting,
@@ -238,136 +249,544 @@ fn get_game_state(area: Area) -> GameState {
     }
 }
 
-fn get_new_state(state: GameState, action: Action) -> GameState {
-    let mut new_state = state.clone();
+// Score bonus is +0/+2/+4 for a completed tree on richness 1/2/3 soil.
+fn richness_bonus(richness: i32) -> i32 {
+    match richness {
+        2 => 2,
+        3 => 4,
+        _ => 0,
+    }
+}
 
+// Applies one player's action to `state`. `is_me` selects whose sun/score/forest
+// ownership the action is charged against, so the same match arms serve both sides
+// of a joint turn.
+fn apply_action(state: &mut GameState, action: Action, is_me: bool) {
     match action {
-        Action::Grow(cell_index) => {}
-        Action::Seed(source_index, target_index) => {}
-        Action::Complete(cell_index) => {}
-        Action::Wait => {}
+        Action::Complete(cell_index) => {
+            let richness = state.area
+                .iter()
+                .find(|cell| cell.index == cell_index)
+                .expect("Could not find cell for COMPLETE action")
+                .richness;
+
+            let gain = state.nutrients + richness_bonus(richness);
+            if is_me {
+                state.score += gain;
+            } else {
+                state.op_score += gain;
+            }
+            state.nutrients -= 1;
+            state.forest.retain(|tree| tree.cell_index != cell_index);
+        }
+        Action::Grow(cell_index) => {
+            let size = state.forest
+                .iter()
+                .find(|tree| tree.cell_index == cell_index)
+                .expect("Could not find tree for GROW action")
+                .size;
+
+            let owned_trees_of_size = |size: i32, forest: &Forest| {
+                forest.iter().filter(|tree| tree.is_mine == is_me && tree.size == size).count() as i32
+            };
+
+            let cost = match size {
+                0 => 1 + owned_trees_of_size(1, &state.forest),
+                1 => 3 + owned_trees_of_size(2, &state.forest),
+                2 => 7 + owned_trees_of_size(3, &state.forest),
+                _ => 0,
+            };
+
+            if is_me {
+                state.sun -= cost;
+            } else {
+                state.op_sun -= cost;
+            }
+
+            let tree = state.forest.iter_mut().find(|tree| tree.cell_index == cell_index).unwrap();
+            tree.size += 1;
+            tree.is_dormant = true;
+        }
+        Action::Seed(source_index, target_index) => {
+            let cost = state.forest
+                .iter()
+                .filter(|tree| tree.is_mine == is_me && tree.size == 0)
+                .count() as i32;
+
+            if is_me {
+                state.sun -= cost;
+            } else {
+                state.op_sun -= cost;
+            }
+
+            let source = state.forest
+                .iter_mut()
+                .find(|tree| tree.cell_index == source_index)
+                .expect("Could not find source tree for SEED action");
+            source.is_dormant = true;
+
+            state.forest.push(Tree {
+                cell_index: target_index,
+                size: 0,
+                is_mine: is_me,
+                is_dormant: true,
+                is_shadowed: false,
+            });
+        }
+        Action::Wait => {
+            if is_me {
+                state.is_waiting = true;
+            } else {
+                state.op_is_waiting = true;
+            }
+        }
     }
+}
+
+fn get_new_state(state: GameState, my_action: Action, op_action: Action) -> GameState {
+    let mut new_state = state.clone();
 
-    new_state.day += 1;
-    new_state.op_is_waiting = false;
+    // Two seeds landing on the same empty cell collide in mid-air: both are lost,
+    // but the sun each player spent sowing them is not refunded.
+    let seed_collision_target = match (my_action, op_action) {
+        (Action::Seed(_, my_target), Action::Seed(_, op_target)) if my_target == op_target =>
+            Some(my_target),
+        _ => None,
+    };
+
+    apply_action(&mut new_state, my_action, true);
+    apply_action(&mut new_state, op_action, false);
+
+    if let Some(target) = seed_collision_target {
+        new_state.forest.retain(|tree| tree.cell_index != target);
+    }
+
+    // The day only advances once both players have ended their turn.
+    if new_state.is_waiting && new_state.op_is_waiting {
+        for tree in new_state.forest.iter_mut() {
+            tree.is_dormant = false;
+        }
+
+        let is_shadowed: Vec<bool> = new_state.forest
+            .iter()
+            .map(|tree| get_is_shadowed(&new_state, tree.size, tree.cell_index))
+            .collect();
+        for (tree, shadowed) in new_state.forest.iter_mut().zip(is_shadowed) {
+            tree.is_shadowed = shadowed;
+        }
+
+        new_state.sun += new_state.forest
+            .iter()
+            .filter(|tree| tree.is_mine && !tree.is_shadowed)
+            .map(|tree| tree.size)
+            .sum::<i32>();
+        new_state.op_sun += new_state.forest
+            .iter()
+            .filter(|tree| !tree.is_mine && !tree.is_shadowed)
+            .map(|tree| tree.size)
+            .sum::<i32>();
+
+        new_state.day += 1;
+        new_state.is_waiting = false;
+        new_state.op_is_waiting = false;
+    }
+
+    // The forest just changed shape, so the legal-action list carried over from
+    // `state` no longer applies; recompute it against the new board.
+    new_state.action_list = get_my_action_list(&new_state);
 
     new_state
 }
 
+// All cell indices reachable from `origin` within `range` hops over the hex grid,
+// used to enumerate the opponent's candidate SEED targets.
+fn cells_within_range(area: &Area, origin: i32, range: i32) -> Vec<i32> {
+    let mut visited = vec![origin];
+    let mut frontier = vec![origin];
+
+    for _ in 0..range {
+        let mut next_frontier = vec![];
+        for &cell_index in frontier.iter() {
+            let cell = area.iter().find(|cell| cell.index == cell_index).unwrap();
+            for &neighbor_index in cell.neighbors_ids.iter() {
+                if neighbor_index != -1 && !visited.contains(&neighbor_index) {
+                    visited.push(neighbor_index);
+                    next_frontier.push(neighbor_index);
+                }
+            }
+        }
+        frontier = next_frontier;
+    }
+
+    visited.retain(|&cell_index| cell_index != origin);
+    visited
+}
+
+// The engine's own `action_list` only reflects the state it was handed with the
+// last input; once the search advances a state with `get_new_state`, it's stale.
+// This recomputes the same legal-action rules against the new board so simulated
+// states stay playable instead of replaying actions against trees that moved on.
+fn get_my_action_list(state: &GameState) -> ActionList {
+    if state.is_waiting {
+        // Already asleep for the day; nothing else is legal until the next day starts.
+        return vec![Action::Wait];
+    }
+
+    let mut actions = vec![Action::Wait];
+
+    let seed_cost = state.forest.iter().filter(|tree| tree.is_mine && tree.size == 0).count() as i32;
+    let my_trees_of_size = |size: i32| {
+        state.forest.iter().filter(|tree| tree.is_mine && tree.size == size).count() as i32
+    };
+
+    for tree in state.forest.iter().filter(|tree| tree.is_mine && !tree.is_dormant) {
+        match tree.size {
+            3 => actions.push(Action::Complete(tree.cell_index)),
+            size @ 0..=2 => {
+                let grow_cost = match size {
+                    0 => 1 + my_trees_of_size(1),
+                    1 => 3 + my_trees_of_size(2),
+                    _ => 7 + my_trees_of_size(3),
+                };
+                if state.sun >= grow_cost {
+                    actions.push(Action::Grow(tree.cell_index));
+                }
+
+                if size >= 1 && state.sun >= seed_cost {
+                    for target in cells_within_range(&state.area, tree.cell_index, size) {
+                        let target_richness = state.area.iter().find(|cell| cell.index == target).unwrap().richness;
+                        let is_occupied = state.forest.iter().any(|tree| tree.cell_index == target);
+                        if target_richness > 0 && !is_occupied {
+                            actions.push(Action::Seed(tree.cell_index, target));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    actions
+}
+
+// The engine only ever tells us our own legal actions, so the opponent's move has
+// to be modelled rather than read from input. This mirrors the rules the game
+// applies to `action_list`, just evaluated against the opponent's forest and sun.
+fn get_op_action_list(state: &GameState) -> ActionList {
+    if state.op_is_waiting {
+        // Already asleep for the day; nothing else is legal until the next day starts.
+        return vec![Action::Wait];
+    }
+
+    let mut actions = vec![Action::Wait];
+
+    let seed_cost = state.forest.iter().filter(|tree| !tree.is_mine && tree.size == 0).count() as i32;
+    let op_trees_of_size = |size: i32| {
+        state.forest.iter().filter(|tree| !tree.is_mine && tree.size == size).count() as i32
+    };
+
+    for tree in state.forest.iter().filter(|tree| !tree.is_mine && !tree.is_dormant) {
+        match tree.size {
+            3 => actions.push(Action::Complete(tree.cell_index)),
+            size @ 0..=2 => {
+                let grow_cost = match size {
+                    0 => 1 + op_trees_of_size(1),
+                    1 => 3 + op_trees_of_size(2),
+                    _ => 7 + op_trees_of_size(3),
+                };
+                if state.op_sun >= grow_cost {
+                    actions.push(Action::Grow(tree.cell_index));
+                }
+
+                if size >= 1 && state.op_sun >= seed_cost {
+                    for target in cells_within_range(&state.area, tree.cell_index, size) {
+                        let target_richness = state.area.iter().find(|cell| cell.index == target).unwrap().richness;
+                        let is_occupied = state.forest.iter().any(|tree| tree.cell_index == target);
+                        if target_richness > 0 && !is_occupied {
+                            actions.push(Action::Seed(tree.cell_index, target));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    actions
+}
+
+// Whether two forests contain the same trees, ignoring order.
+fn forests_match(a: &Forest, b: &Forest) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut a_keys: Vec<(i32, i32, bool, bool)> = a
+        .iter()
+        .map(|tree| (tree.cell_index, tree.size, tree.is_mine, tree.is_dormant))
+        .collect();
+    let mut b_keys: Vec<(i32, i32, bool, bool)> = b
+        .iter()
+        .map(|tree| (tree.cell_index, tree.size, tree.is_mine, tree.is_dormant))
+        .collect();
+    a_keys.sort();
+    b_keys.sort();
+
+    a_keys == b_keys
+}
+
+// Whether `observed` is the same position `simulated` predicted, so a cached subtree
+// can be promoted instead of searching from scratch.
+fn game_states_match(simulated: &GameState, observed: &GameState) -> bool {
+    simulated.day == observed.day &&
+        simulated.nutrients == observed.nutrients &&
+        simulated.sun == observed.sun &&
+        simulated.score == observed.score &&
+        simulated.op_sun == observed.op_sun &&
+        simulated.op_score == observed.op_score &&
+        forests_match(&simulated.forest, &observed.forest)
+}
+
 // ================================================================================================
 // MCTS
 // ================================================================================================
 
+// A contiguous range of child ids inside `SearchTree::nodes`. Every node's children are
+// pushed to the arena back-to-back when the node is expanded, so a node only needs
+// to remember where its slice starts and ends.
+#[derive(Clone, Copy)]
+struct IdxRange {
+    start: usize,
+    end: usize,
+}
+
+impl IdxRange {
+    fn iter(&self) -> std::ops::Range<usize> {
+        self.start..self.end
+    }
+}
+
 #[derive(Clone)]
-// TODO: I should change parent and children to indexes instead,
-// because storing nodes (that also store nodes, that also store nodes, etc) will be a lot of memory (and slow)
-// it may also help my mutablility issues
-struct Node<'a> {
-    parent: Option<&'a Node<'a>>,
-    children: Vec<Node<'a>>,
+struct Node {
+    parent: Option<usize>,
+    children: Option<IdxRange>,
     action: Action,
     visits: i32,
     wins: i32,
     state: GameState,
 }
 
-fn mcts(root_node: &Node, iterations: i32) -> Action {
-    for _ in 0..iterations {
-        let leaf_node = traverse(root_node);
-        leaf_node.visits += 1;
-        let is_win = rollout(leaf_node);
+// Flat arena backing the MCTS tree. Nodes reference each other by index instead of
+// by reference, so the tree can be grown and traversed without fighting the borrow
+// checker over aliased mutable references.
+struct SearchTree {
+    nodes: Vec<Node>,
+}
+
+impl SearchTree {
+    fn new(root_state: GameState) -> Self {
+        let root = Node {
+            parent: None,
+            children: None,
+            action: Action::Wait,
+            visits: 0,
+            wins: 0,
+            state: root_state,
+        };
+        SearchTree { nodes: vec![root] }
+    }
+}
+
+// Finds the child of `root_id` that was reached by playing `played_action` and whose
+// simulated state matches what the engine actually reports. Returns `None` if the
+// opponent's hidden move produced a board we never searched.
+fn find_reused_root(
+    tree: &SearchTree,
+    root_id: usize,
+    played_action: Action,
+    observed_state: &GameState
+) -> Option<usize> {
+    let children = tree.nodes[root_id].children?;
+    children
+        .iter()
+        .find(|&child_id| {
+            tree.nodes[child_id].action == played_action &&
+                game_states_match(&tree.nodes[child_id].state, observed_state)
+        })
+}
+
+// CodinGame allows ~100ms per turn, and a much larger allowance on turn 0 while the
+// referee is still warming up.
+const TURN_BUDGET: Duration = Duration::from_millis(100);
+const FIRST_TURN_BUDGET: Duration = Duration::from_millis(1000);
+const SEARCH_SAFETY_MARGIN: Duration = Duration::from_millis(10);
+
+fn mcts(tree: &mut SearchTree, root_id: usize, start: Instant, budget: Duration, rng: &mut StdRng) -> Action {
+    let deadline = start + budget.saturating_sub(SEARCH_SAFETY_MARGIN);
+
+    while Instant::now() < deadline {
+        let leaf_id = traverse(tree, root_id, rng);
+        tree.nodes[leaf_id].visits += 1;
+        let is_win = rollout(&tree.nodes[leaf_id], rng);
 
         if is_win {
-            leaf_node.wins += 1;
+            tree.nodes[leaf_id].wins += 1;
         }
 
-        backpropagate(leaf_node, is_win);
+        backpropagate(tree, leaf_id, is_win);
     }
 
-    return best_action(root_node);
+    best_action(tree, root_id)
 }
 
-fn traverse<'a>(root_node: &'a mut Node<'a>) -> &'a mut Node<'a> {
-    let mut current_node = root_node;
+fn traverse(tree: &mut SearchTree, root_id: usize, rng: &mut StdRng) -> usize {
+    let mut current_id = root_id;
 
-    while is_fully_expanded(current_node) {
-        current_node = select_child_by_utc(current_node);
+    while tree.nodes[current_id].children.is_some() {
+        current_id = select_child_by_uct(tree, current_id);
     }
 
-    // If the game day is  23, then the game is over
-    if current_node.state.day == 23 {
-        return current_node;
+    // If the game day is 23, then the game is over
+    if tree.nodes[current_id].state.day == 23 {
+        return current_id;
     }
 
-    return expand_and_select_child(current_node);
+    expand_and_select_child(tree, current_id, rng)
 }
 
-fn is_fully_expanded(node: &Node) -> bool {
-    node.children.len() == node.state.action_list.len()
-}
-
-fn expand_and_select_child<'a>(parent_node: &'a mut Node) -> &'a mut Node<'a> {
-    let unused_actions: Vec<Action> = parent_node.state.action_list
-        .iter()
-        .filter(|action| { !parent_node.children.iter().any(|child| child.action == **action) })
-        .cloned()
-        .collect();
+fn expand_and_select_child(tree: &mut SearchTree, parent_id: usize, rng: &mut StdRng) -> usize {
+    let actions = tree.nodes[parent_id].state.action_list.clone();
+    let parent_state = tree.nodes[parent_id].state.clone();
+
+    // The opponent's move this turn is unknown, so sample one candidate and pair
+    // it with each of my own candidate actions to form joint actions.
+    let op_action = *get_op_action_list(&parent_state).choose(rng).unwrap();
+
+    let start = tree.nodes.len();
+    for action in actions.iter() {
+        let new_state = get_new_state(parent_state.clone(), *action, op_action);
+        tree.nodes.push(Node {
+            parent: Some(parent_id),
+            children: None,
+            action: *action,
+            visits: 0,
+            wins: 0,
+            state: new_state,
+        });
+    }
+    let end = tree.nodes.len();
 
-    let random_action: &Action = unused_actions.choose(&mut rand::thread_rng()).unwrap();
-    let mut new_state = get_new_state(parent_node.state, *random_action);
+    tree.nodes[parent_id].children = Some(IdxRange { start, end });
 
-    let new_node = Node {
-        parent: Some(parent_node),
-        children: vec![],
-        action: *random_action,
-        visits: 0,
-        wins: 0,
-        state: new_state,
-    };
-
-    parent_node.children.push(new_node);
-    return parent_node.children.last_mut().unwrap();
+    select_child_by_uct(tree, parent_id)
 }
 
-fn rollout(node: &Node) -> bool {
+fn rollout(node: &Node, rng: &mut StdRng) -> bool {
     let mut current_node = node.clone();
     while current_node.state.day <= 23 {
-        current_node = rollout_policy(current_node);
+        current_node = rollout_policy(current_node, rng);
+    }
+    current_node.state.score > current_node.state.op_score
+}
+
+// Purely random rollouts meander over a 24-day horizon and rarely produce a useful
+// score signal, so bias the sampling toward actions that look purposeful instead.
+const WAIT_BASE_WEIGHT: f32 = 1.0;
+const WAIT_SUN_SCARCE_BONUS: f32 = 4.0;
+const SUN_SCARCE_THRESHOLD: i32 = 3;
+
+const COMPLETE_BASE_WEIGHT: f32 = 1.0;
+const COMPLETE_LATE_GAME_DAY: i32 = 18;
+const COMPLETE_RICHNESS_WEIGHT: f32 = 3.0;
+
+const GROW_BASE_WEIGHT: f32 = 1.0;
+const GROW_SUN_PLENTY_THRESHOLD: i32 = 6;
+const GROW_SUN_PLENTY_BONUS: f32 = 2.0;
+
+const SEED_BASE_WEIGHT: f32 = 1.0;
+const SEED_EARLY_GAME_DAY: i32 = 10;
+const SEED_RICHNESS_3_BONUS: f32 = 3.0;
+const SEED_FAR_FROM_OWN_BONUS: f32 = 2.0;
+const SEED_PROXIMITY_RADIUS: i32 = 2;
+
+// Whether `target` falls outside `SEED_PROXIMITY_RADIUS` hops of every tree we own.
+fn is_far_from_own_trees(state: &GameState, target: i32) -> bool {
+    !state.forest
+        .iter()
+        .filter(|tree| tree.is_mine)
+        .any(|tree| cells_within_range(&state.area, tree.cell_index, SEED_PROXIMITY_RADIUS).contains(&target))
+}
+
+fn rollout_action_weight(state: &GameState, action: Action) -> f32 {
+    match action {
+        Action::Wait => {
+            if state.sun < SUN_SCARCE_THRESHOLD {
+                WAIT_BASE_WEIGHT + WAIT_SUN_SCARCE_BONUS
+            } else {
+                WAIT_BASE_WEIGHT
+            }
+        }
+        Action::Complete(cell_index) => {
+            let mut weight = COMPLETE_BASE_WEIGHT;
+            if state.day >= COMPLETE_LATE_GAME_DAY {
+                let richness = state.area.iter().find(|cell| cell.index == cell_index).unwrap().richness;
+                weight += COMPLETE_RICHNESS_WEIGHT * richness as f32;
+            }
+            weight
+        }
+        Action::Grow(_) => {
+            if state.sun >= GROW_SUN_PLENTY_THRESHOLD {
+                GROW_BASE_WEIGHT + GROW_SUN_PLENTY_BONUS
+            } else {
+                GROW_BASE_WEIGHT
+            }
+        }
+        Action::Seed(_, target_index) => {
+            let mut weight = SEED_BASE_WEIGHT;
+            if state.day <= SEED_EARLY_GAME_DAY {
+                let richness = state.area.iter().find(|cell| cell.index == target_index).unwrap().richness;
+                if richness == 3 {
+                    weight += SEED_RICHNESS_3_BONUS;
+                }
+                if is_far_from_own_trees(state, target_index) {
+                    weight += SEED_FAR_FROM_OWN_BONUS;
+                }
+            }
+            weight
+        }
     }
-    return current_node.state.score > current_node.state.op_score;
 }
 
-fn rollout_policy(node: Node) -> Node {
-    // From this node, choose a random action, and get the new state, and return the new node (with the new state)
-    let random_action: &Action = node.state.action_list.choose(&mut rand::thread_rng()).unwrap();
-    let mut new_state = get_new_state(node.state.clone(), *random_action);
+fn rollout_policy(node: Node, rng: &mut StdRng) -> Node {
+    // From this node, choose a weighted-random action, and get the new state, and return the new node (with the new state)
+    let random_action = *node.state.action_list
+        .choose_weighted(rng, |action| rollout_action_weight(&node.state, *action))
+        .unwrap();
+    let random_op_action = *get_op_action_list(&node.state).choose(rng).unwrap();
+    let new_state = get_new_state(node.state.clone(), random_action, random_op_action);
 
-    let new_node = Node {
+    Node {
         parent: None,
-        children: vec![],
-        action: *random_action,
+        children: None,
+        action: random_action,
         visits: 0,
         wins: 0,
         state: new_state,
-    };
-
-    return new_node;
+    }
 }
 
-fn backpropagate(node: &mut Node, is_win: bool) {
-    if node.parent.is_none() {
-        // This is the root node
-        return;
-    }
+fn backpropagate(tree: &mut SearchTree, node_id: usize, is_win: bool) {
+    let mut current_id = tree.nodes[node_id].parent;
 
-    let node_parent = node.parent.as_mut().unwrap();
-    node_parent.visits += 1;
+    while let Some(id) = current_id {
+        tree.nodes[id].visits += 1;
 
-    if is_win {
-        node_parent.wins += 1;
-    }
+        if is_win {
+            tree.nodes[id].wins += 1;
+        }
 
-    backpropagate(node_parent, is_win)
+        current_id = tree.nodes[id].parent;
+    }
 }
 
 fn uct_value(current_node: &Node, parent_node: &Node, explore_rate: f32) -> f32 {
@@ -384,28 +803,31 @@ fn uct_value(current_node: &Node, parent_node: &Node, explore_rate: f32) -> f32
     wins / visits + explore_rate * (parent_visits.ln() / visits).sqrt()
 }
 
-fn select_child_by_utc<'a>(node: &'a Node<'a>) -> &'a Node<'a> {
-    let mut best_utc = f32::NEG_INFINITY;
-    let mut best_child_index = 0;
+fn select_child_by_uct(tree: &SearchTree, node_id: usize) -> usize {
+    let children = tree.nodes[node_id].children.expect("Node has not been expanded yet");
+
+    let mut best_uct = f32::NEG_INFINITY;
+    let mut best_child_id = children.start;
 
-    for (i, child) in node.children.iter().enumerate() {
-        let utc = uct_value(child, node, 1.0);
-        if utc > best_utc {
-            best_utc = utc;
-            best_child_index = i;
+    for child_id in children.iter() {
+        let uct = uct_value(&tree.nodes[child_id], &tree.nodes[node_id], 1.0);
+        if uct > best_uct {
+            best_uct = uct;
+            best_child_id = child_id;
         }
     }
 
-    // Get a mutable reference to the best child
-    return node.children.get(best_child_index).unwrap();
+    best_child_id
 }
 
-fn best_action(node: &Node) -> Action {
+fn best_action(tree: &SearchTree, node_id: usize) -> Action {
+    let children = tree.nodes[node_id].children.expect("Root node has not been expanded yet");
+
     // Find the child with the most visits
-    node.children
-        .iter()
-        .max_by_key(|child| child.visits)
-        .unwrap().action
+    children.iter()
+        .max_by_key(|&child_id| tree.nodes[child_id].visits)
+        .map(|child_id| tree.nodes[child_id].action)
+        .unwrap()
 }
 
 /*
@@ -428,20 +850,115 @@ Things we will need for simulation:
 // Main
 // ================================================================================================
 
+// Toggle between the two search drivers without touching the game loop below.
+const USE_MINIMAX: bool = false;
+
 fn main() {
     let area = get_area();
+    let mut previous_turn: Option<(SearchTree, usize, Action)> = None;
+    let mut rng = StdRng::seed_from_u64(INIT_SEED);
 
     // game loop
     loop {
-        let answer = String::from("WAIT");
-
         let state = get_game_state(area);
 
-        for a in state.action_list.iter() {
-            eprintln!("Action : {}", a);
-        }
+        let turn_start = Instant::now();
+        let budget = if state.day == 0 { FIRST_TURN_BUDGET } else { TURN_BUDGET };
+
+        let action = if USE_MINIMAX {
+            previous_turn = None;
+            minimax::choose_action(&state, turn_start, budget)
+        } else {
+            let (mut tree, root_id) = match previous_turn.take() {
+                Some((old_tree, old_root_id, played_action)) =>
+                    match find_reused_root(&old_tree, old_root_id, played_action, &state) {
+                        Some(reused_root_id) => (old_tree, reused_root_id),
+                        None => (SearchTree::new(state.clone()), 0),
+                    }
+                None => (SearchTree::new(state.clone()), 0),
+            };
+
+            // The reused root was simulated from a guessed opponent move; sync it
+            // with what the engine actually reports before searching from it.
+            tree.nodes[root_id].state.action_list = state.action_list;
+            tree.nodes[root_id].state.is_waiting = false;
+            tree.nodes[root_id].state.op_is_waiting = state.op_is_waiting;
+
+            let action = mcts(&mut tree, root_id, turn_start, budget, &mut rng);
+            previous_turn = Some((tree, root_id, action));
+            action
+        };
 
         // GROW cellIdx | SEED sourceIdx targetIdx | COMPLETE cellIdx | WAIT <message>
-        println!("{}", answer);
+        println!("{}", action);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_area() -> Area {
+        [Cell::new(); 37]
+    }
+
+    fn single_tree_state(is_mine: bool, is_waiting: bool, op_is_waiting: bool) -> GameState {
+        let forest = vec![Tree { cell_index: 0, size: 1, is_mine, is_dormant: false, is_shadowed: false }];
+        let mut state = GameState {
+            day: 0,
+            nutrients: 20,
+            sun: 10,
+            score: 0,
+            is_waiting,
+            op_sun: 10,
+            op_score: 0,
+            op_is_waiting,
+            area: empty_area(),
+            forest,
+            action_list: vec![],
+        };
+        state.action_list = get_my_action_list(&state);
+        state
+    }
+
+    #[test]
+    fn my_action_list_is_wait_only_once_asleep() {
+        let state = single_tree_state(true, true, false);
+        assert!(get_my_action_list(&state) == vec![Action::Wait]);
+    }
+
+    #[test]
+    fn op_action_list_is_wait_only_once_asleep() {
+        let state = single_tree_state(false, false, true);
+        assert!(get_op_action_list(&state) == vec![Action::Wait]);
+    }
+
+    #[test]
+    fn get_new_state_refreshes_action_list_and_advances_day_once_both_wait() {
+        // Opponent already ended their day; waiting here should finally advance the day.
+        let state = single_tree_state(true, false, true);
+
+        let new_state = get_new_state(state, Action::Wait, Action::Wait);
+
+        assert_eq!(new_state.day, 1);
+        assert!(!new_state.is_waiting);
+        assert!(!new_state.op_is_waiting);
+        // The tree is no longer dormant and it's a fresh day, so Grow is legal again.
+        assert!(new_state.action_list.contains(&Action::Grow(0)));
+    }
+
+    #[test]
+    fn rollout_policy_is_deterministic_for_a_fixed_seed() {
+        let state = single_tree_state(true, false, false);
+        let node = Node { parent: None, children: None, action: Action::Wait, visits: 0, wins: 0, state };
+
+        let mut rng_a = StdRng::seed_from_u64(42);
+        let mut rng_b = StdRng::seed_from_u64(42);
+        let result_a = rollout_policy(node.clone(), &mut rng_a);
+        let result_b = rollout_policy(node.clone(), &mut rng_b);
+
+        assert!(result_a.action == result_b.action);
+        assert_eq!(result_a.state.sun, result_b.state.sun);
+        assert_eq!(result_a.state.forest.len(), result_b.state.forest.len());
     }
 }
@@ -0,0 +1,176 @@
+// Depth-limited minimax with alpha-beta pruning, offered as an alternative to the
+// MCTS driver. With only 24 days on the clock a shallow exhaustive search can
+// outplay a shallow random-rollout one, at the cost of needing a hand-tuned
+// evaluation function instead of a win/loss signal.
+use std::time::{ Duration, Instant };
+
+use crate::{ Action, ActionList, GameState, Tree, get_new_state, get_op_action_list, SEARCH_SAFETY_MARGIN };
+
+pub const DEFAULT_DEPTH: i32 = 4;
+
+const TREE_SIZE_WEIGHT: f32 = 1.5;
+const SUN_INCOME_WEIGHT: f32 = 0.5;
+const RICHNESS_WEIGHT: f32 = 0.25;
+
+// Static evaluation of a leaf state, from my perspective: positive favors me.
+fn evaluate(state: &GameState) -> f32 {
+    let material = (state.score - state.op_score) as f32;
+
+    let tree_size_score = sum_for_mine(state, |tree| tree.size as f32)
+        - sum_for_mine_negated(state, |tree| tree.size as f32);
+
+    let sun_income_score = sum_for_mine(state, |tree| if tree.is_shadowed { 0.0 } else { tree.size as f32 })
+        - sum_for_mine_negated(state, |tree| if tree.is_shadowed { 0.0 } else { tree.size as f32 });
+
+    let richness_score = sum_for_mine(state, |tree| richness_of(state, tree.cell_index) as f32)
+        - sum_for_mine_negated(state, |tree| richness_of(state, tree.cell_index) as f32);
+
+    material
+        + TREE_SIZE_WEIGHT * tree_size_score
+        + SUN_INCOME_WEIGHT * sun_income_score
+        + RICHNESS_WEIGHT * richness_score
+}
+
+fn richness_of(state: &GameState, cell_index: i32) -> i32 {
+    state.area.iter().find(|cell| cell.index == cell_index).unwrap().richness
+}
+
+fn sum_for_mine(state: &GameState, f: impl Fn(&Tree) -> f32) -> f32 {
+    state.forest.iter().filter(|tree| tree.is_mine).map(f).sum()
+}
+
+fn sum_for_mine_negated(state: &GameState, f: impl Fn(&Tree) -> f32) -> f32 {
+    state.forest.iter().filter(|tree| !tree.is_mine).map(f).sum()
+}
+
+// Cheap ordering so alpha-beta prunes as much of the tree as possible: completing
+// and growing tend to matter more than seeding or waiting.
+fn action_priority(action: Action) -> i32 {
+    match action {
+        Action::Complete(_) => 3,
+        Action::Grow(_) => 2,
+        Action::Seed(_, _) => 1,
+        Action::Wait => 0,
+    }
+}
+
+fn sorted_by_priority(mut actions: ActionList) -> ActionList {
+    actions.sort_by_key(|action| std::cmp::Reverse(action_priority(*action)));
+    actions
+}
+
+// Value of `state` to me, `depth` joint-action plies from here. Since the game is
+// simultaneous rather than turn-based, each ply is a small matrix game: I pick the
+// action that maximizes my worst case against whatever the opponent picks.
+//
+// `deadline` bounds the same wall clock `choose_action` is searching against;
+// once it passes, every remaining ply is scored as if it were a leaf instead of
+// expanded further, so a slow depth can't blow through the turn budget.
+fn value(state: &GameState, depth: i32, mut alpha: f32, beta: f32, deadline: Instant) -> f32 {
+    if depth == 0 || state.day >= 23 || Instant::now() >= deadline {
+        return evaluate(state);
+    }
+
+    let my_actions = sorted_by_priority(state.action_list.clone());
+    let op_actions = sorted_by_priority(get_op_action_list(state));
+
+    let mut best = f32::NEG_INFINITY;
+
+    for my_action in my_actions {
+        let mut worst_for_me = f32::INFINITY;
+
+        for &op_action in op_actions.iter() {
+            let next_state = get_new_state(state.clone(), my_action, op_action);
+            let score = value(&next_state, depth - 1, alpha, worst_for_me, deadline);
+
+            if score < worst_for_me {
+                worst_for_me = score;
+            }
+            if worst_for_me <= alpha {
+                // The opponent can already hold me at or below alpha here, so this
+                // action can't beat alpha and its remaining replies are skipped.
+                break;
+            }
+        }
+
+        if worst_for_me > best {
+            best = worst_for_me;
+        }
+        if best > alpha {
+            alpha = best;
+        }
+        if alpha >= beta {
+            break;
+        }
+    }
+
+    best
+}
+
+// One full my_actions x op_actions pass at a fixed depth, used by `choose_action`'s
+// iterative-deepening loop below.
+fn search_at_depth(state: &GameState, my_actions: &ActionList, depth: i32, deadline: Instant) -> Action {
+    let op_actions = sorted_by_priority(get_op_action_list(state));
+
+    let mut best_action = my_actions[0];
+    let mut best_value = f32::NEG_INFINITY;
+    let mut alpha = f32::NEG_INFINITY;
+    let beta = f32::INFINITY;
+
+    for &my_action in my_actions.iter() {
+        if Instant::now() >= deadline {
+            break;
+        }
+
+        let mut worst_for_me = f32::INFINITY;
+
+        for &op_action in op_actions.iter() {
+            let next_state = get_new_state(state.clone(), my_action, op_action);
+            let score = value(&next_state, depth - 1, alpha, worst_for_me, deadline);
+
+            if score < worst_for_me {
+                worst_for_me = score;
+            }
+            if worst_for_me <= alpha {
+                break;
+            }
+        }
+
+        if worst_for_me > best_value {
+            best_value = worst_for_me;
+            best_action = my_action;
+        }
+        if best_value > alpha {
+            alpha = best_value;
+        }
+        if alpha >= beta {
+            break;
+        }
+    }
+
+    best_action
+}
+
+// Searches increasingly deep until `DEFAULT_DEPTH` or the turn budget runs out,
+// mirroring the wall-clock discipline `mcts` uses elsewhere: a shallower
+// complete result beats a deeper one that never finished.
+pub fn choose_action(state: &GameState, start: Instant, budget: Duration) -> Action {
+    let deadline = start + budget.saturating_sub(SEARCH_SAFETY_MARGIN);
+    let my_actions = sorted_by_priority(state.action_list.clone());
+
+    let mut best_action = my_actions[0];
+
+    for depth in 1..=DEFAULT_DEPTH {
+        if Instant::now() >= deadline {
+            break;
+        }
+        let action = search_at_depth(state, &my_actions, depth, deadline);
+        if depth > 1 && Instant::now() >= deadline {
+            // This depth was cut off by the deadline; keep the last one that finished.
+            break;
+        }
+        best_action = action;
+    }
+
+    best_action
+}